@@ -0,0 +1,42 @@
+// Copyright (c) Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+pub type CliTypedResult<T> = Result<T, CliError>;
+
+/// A command that can be run from the Aptos CLI, returning a typed result
+/// that the CLI's top-level runner renders (as plain text or JSON).
+#[async_trait]
+pub trait CliCommand<T: Send>: Sized + Send {
+    fn command_name(&self) -> &'static str;
+
+    async fn execute(self) -> CliTypedResult<T>;
+}
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("Error accessing '{0}': {1}")]
+    IO(String, #[source] std::io::Error),
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+    /// Returned by `aptos move fmt --check` (and `--emit-mode=check`) when
+    /// one or more files would be reformatted. Carries the offending file
+    /// paths so callers can report them without re-parsing CLI output.
+    #[error("The following files are not formatted: {0:?}")]
+    FormattingNotUpToDate(Vec<String>),
+}
+
+impl CliError {
+    /// The process exit code `main` should return for this error.
+    /// `FormattingNotUpToDate` gets its own code, distinct from the generic
+    /// failure code, so CI can tell "needs reformatting" apart from other
+    /// kinds of failures (e.g. a crashed formatter or bad arguments).
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::FormattingNotUpToDate(_) => 3,
+            _ => 1,
+        }
+    }
+}